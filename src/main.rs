@@ -1,32 +1,218 @@
-use byteorder::{LittleEndian, ReadBytesExt};
-use hex_slice::AsHex;
-use positioned_io::{Cursor, ReadAt, Slice};
-use std::{fs::OpenOptions, vec};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::size_of;
 
-use color_eyre::Result;
 use custom_debug::Debug as CustomDebug;
+use zerocopy::byteorder::little_endian::{U16, U32};
+use zerocopy::{FromBytes, FromZeroes};
+
+#[cfg(feature = "std")]
+use color_eyre::Result;
+#[cfg(feature = "std")]
+use lru::LruCache;
+#[cfg(feature = "std")]
+use positioned_io::ReadAt;
+#[cfg(feature = "std")]
+use std::fs::OpenOptions;
+#[cfg(feature = "std")]
+use std::num::NonZeroUsize;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(not(feature = "std"))]
+pub type Result<T> = core::result::Result<T, Error>;
 
-struct Reader<IO> {
-    inner: IO,
+/// Minimal error type used when the crate is built without `std` (and hence
+/// without `color_eyre`).
+#[cfg(not(feature = "std"))]
+#[derive(Debug)]
+pub struct Error;
+
+/// Random-access, read-only storage. This is the only thing the reader needs
+/// from the outside world, so the crate can run on a `std::fs::File`, an
+/// in-memory byte slice, or a kernel block device without caring which.
+pub trait Volume {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<()>;
+
+    fn len(&self) -> Result<u64>;
+
+    fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
 }
 
-impl<IO: ReadAt> Reader<IO> {
-    fn new(inner: IO) -> Self {
-        Self { inner }
+/// A `std::fs::File` is the canonical volume in the `std` build. We deliberately
+/// don't blanket-impl over every `positioned_io::ReadAt` type: that also covers
+/// reference types like `&mut [u8]`, whose `Volume::len` would then shadow the
+/// inherent slice `len()` at every call site.
+#[cfg(feature = "std")]
+impl Volume for std::fs::File {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        self.read_exact_at(offset, buf)?;
+        Ok(())
     }
 
-    fn u16(&self, offset: u64) -> Result<u16> {
-        let mut cursor = Cursor::new_pos(&self.inner, offset);
-        Ok(cursor.read_u16::<LittleEndian>()?)
+    fn len(&self) -> Result<u64> {
+        Ok(positioned_io::Size::size(self)?.unwrap_or(0))
     }
+}
 
-    fn u32(&self, offset: u64) -> Result<u32> {
-        let mut cursor = Cursor::new_pos(&self.inner, offset);
-        Ok(cursor.read_u32::<LittleEndian>()?)
+/// In the `no_std` build, an in-memory image is the canonical volume.
+#[cfg(not(feature = "std"))]
+impl Volume for [u8] {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let start = offset as usize;
+        let end = start.checked_add(buf.len()).ok_or(Error)?;
+        buf.copy_from_slice(self.get(start..end).ok_or(Error)?);
+        Ok(())
     }
 
-    fn u64_lohi(&self, lo: u64, hi: u64) -> Result<u64> {
-        Ok(self.u32(lo)? as u64 + ((self.u32(hi)? as u64) << 32))
+    fn len(&self) -> Result<u64> {
+        Ok(<[u8]>::len(self) as u64)
+    }
+}
+
+/// Marker trait describing the sector size a volume is addressed in, as a
+/// zero-sized type parameter so the choice is monomorphised away.
+pub trait SectorSize {
+    const LOG_SIZE: u32;
+    const SIZE: u64 = 1 << Self::LOG_SIZE;
+}
+
+pub struct Size512;
+pub struct Size2048;
+pub struct Size4096;
+
+impl SectorSize for Size512 {
+    const LOG_SIZE: u32 = 9;
+}
+impl SectorSize for Size2048 {
+    const LOG_SIZE: u32 = 11;
+}
+impl SectorSize for Size4096 {
+    const LOG_SIZE: u32 = 12;
+}
+
+/// Reads a fixed byte span from a volume, relative to a base offset, and
+/// reinterprets it as an on-disk `#[repr(C)]` struct — one read per structure.
+///
+/// Generic over the volume `V` (static dispatch, so it also serves `?Sized`
+/// volumes like `[u8]`) and over the volume's [`SectorSize`] `S`: block devices
+/// only allow sector-granular reads, so the reader reads the whole run of
+/// sectors covering the struct and slices the field bytes back out.
+struct Reader<'a, V: ?Sized, S> {
+    volume: &'a V,
+    base: u64,
+    _sector: core::marker::PhantomData<S>,
+}
+
+impl<'a, V: Volume + ?Sized, S: SectorSize> Reader<'a, V, S> {
+    fn new(volume: &'a V, base: u64) -> Self {
+        Self {
+            volume,
+            base,
+            _sector: core::marker::PhantomData,
+        }
+    }
+
+    fn struct_at<T: FromBytes>(&self, offset: u64) -> Result<T> {
+        let size = size_of::<T>() as u64;
+        let start = self.base + offset;
+        let first = start >> S::LOG_SIZE;
+        let last = (start + size - 1) >> S::LOG_SIZE;
+        let mut raw = vec![0u8; ((last - first + 1) * S::SIZE) as usize];
+        self.volume.read_at(first << S::LOG_SIZE, &mut raw)?;
+        let within = (start - (first << S::LOG_SIZE)) as usize;
+        Ok(T::read_from(&raw[within..within + size as usize])
+            .expect("buffer is exactly sized for T"))
+    }
+}
+
+/// On-disk `ext4_super_block` layout, little-endian, cast directly from bytes.
+#[derive(FromBytes, FromZeroes)]
+#[repr(C)]
+struct SuperblockRaw {
+    inodes_count: U32,
+    blocks_count_lo: U32,
+    r_blocks_count_lo: U32,
+    free_blocks_count_lo: U32,
+    free_inodes_count: U32,
+    first_data_block: U32,
+    log_block_size: U32,
+    log_cluster_size: U32,
+    blocks_per_group: U32,
+    clusters_per_group: U32,
+    inodes_per_group: U32,
+    mtime: U32,
+    wtime: U32,
+    mnt_count: U16,
+    max_mnt_count: U16,
+    magic: U16,
+    state: U16,
+    errors: U16,
+    minor_rev_level: U16,
+    lastcheck: U32,
+    checkinterval: U32,
+    creator_os: U32,
+    rev_level: U32,
+    def_resuid: U16,
+    def_resgid: U16,
+    first_ino: U32,
+    inode_size: U16,
+    block_group_nr: U16,
+    feature_compat: U32,
+    feature_incompat: U32,
+    feature_ro_compat: U32,
+    uuid: [u8; 16],
+    volume_name: [u8; 16],
+    last_mounted: [u8; 64],
+    algorithm_usage_bitmap: U32,
+    prealloc_blocks: u8,
+    prealloc_dir_blocks: u8,
+    reserved_gdt_blocks: U16,
+    journal_uuid: [u8; 16],
+    journal_inum: U32,
+    journal_dev: U32,
+    last_orphan: U32,
+    hash_seed: [u8; 16],
+    def_hash_version: u8,
+    jnl_backup_type: u8,
+    desc_size: U16,
+    default_mount_opts: U32,
+    first_meta_bg: U32,
+    mkfs_time: U32,
+    jnl_blocks: [u8; 68],
+    blocks_count_hi: U32,
+}
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy)]
+    struct FeatureCompat: u32 {
+        const HAS_JOURNAL = 0x4;
+        const EXT_ATTR = 0x8;
+        const RESIZE_INODE = 0x10;
+        const DIR_INDEX = 0x20;
+        const SPARSE_SUPER2 = 0x200;
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct FeatureIncompat: u32 {
+        const FILETYPE = 0x2;
+        const EXTENTS = 0x40;
+        const SIXTY_FOUR_BIT = 0x80;
+        const FLEX_BG = 0x200;
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct FeatureRoCompat: u32 {
+        const SPARSE_SUPER = 0x1;
+        const LARGE_FILE = 0x2;
+        const METADATA_CSUM = 0x400;
     }
 }
 
@@ -34,23 +220,81 @@ impl<IO: ReadAt> Reader<IO> {
 struct Superblock {
     #[debug(format = "{:x}")]
     magic: u16,
+    uuid: uuid::Uuid,
     block_size: u64,
+    first_data_block: u64,
+    blocks_count: u64,
+    inodes_count: u64,
     blocks_per_group: u64,
     inodes_per_group: u64,
     inode_size: u64,
+    feature_compat: FeatureCompat,
+    feature_incompat: FeatureIncompat,
+    feature_ro_compat: FeatureRoCompat,
 }
 
 impl Superblock {
-    fn new(dev: &dyn ReadAt) -> Result<Self> {
-        let r = Reader::new(Slice::new(dev, 1024, None));
+    fn new<V: Volume + ?Sized, S: SectorSize>(dev: &V) -> Result<Self> {
+        let raw: SuperblockRaw = Reader::<V, S>::new(dev, 1024).struct_at(0)?;
         Ok(Self {
-            magic: r.u16(0x38)?,
-            block_size: (2u32.pow(10 + r.u32(0x18)?)) as u64,
-            blocks_per_group: r.u32(0x20)? as u64,
-            inodes_per_group: r.u32(0x28)? as u64,
-            inode_size: r.u16(0x58)? as u64,
+            magic: raw.magic.get(),
+            uuid: uuid::Uuid::from_bytes(raw.uuid),
+            block_size: 2u64.pow(10 + raw.log_block_size.get()),
+            first_data_block: raw.first_data_block.get() as u64,
+            blocks_count: raw.blocks_count_lo.get() as u64
+                | ((raw.blocks_count_hi.get() as u64) << 32),
+            inodes_count: raw.inodes_count.get() as u64,
+            blocks_per_group: raw.blocks_per_group.get() as u64,
+            inodes_per_group: raw.inodes_per_group.get() as u64,
+            inode_size: raw.inode_size.get() as u64,
+            feature_compat: FeatureCompat::from_bits_retain(raw.feature_compat.get()),
+            feature_incompat: FeatureIncompat::from_bits_retain(raw.feature_incompat.get()),
+            feature_ro_compat: FeatureRoCompat::from_bits_retain(raw.feature_ro_compat.get()),
         })
     }
+
+    /// Compute the number of block groups two independent ways — from the block
+    /// count and from the inode count — and reject the superblock if they
+    /// disagree, so a corrupt or misread superblock is caught here rather than
+    /// producing wrong descriptor offsets later.
+    fn block_group_count(&self) -> Result<u64> {
+        // Block numbering starts at `first_data_block`, so only the blocks after
+        // it are divided into groups; counting from zero overshoots by one group
+        // whenever `blocks_count ≡ first_data_block (mod blocks_per_group)`.
+        let by_blocks =
+            (self.blocks_count - self.first_data_block).div_ceil(self.blocks_per_group);
+        let by_inodes = self.inodes_count.div_ceil(self.inodes_per_group);
+        if by_blocks != by_inodes {
+            #[cfg(feature = "std")]
+            return Err(color_eyre::eyre::eyre!(
+                "inconsistent block group count: {by_blocks} by blocks vs {by_inodes} by inodes"
+            ));
+            #[cfg(not(feature = "std"))]
+            return Err(Error);
+        }
+        Ok(by_blocks)
+    }
+}
+
+/// On-disk 64-bit `ext4_group_desc` layout, cast directly from bytes.
+#[derive(FromBytes, FromZeroes)]
+#[repr(C)]
+struct BlockGroupDescriptorRaw {
+    block_bitmap_lo: U32,
+    inode_bitmap_lo: U32,
+    inode_table_lo: U32,
+    free_blocks_count_lo: U16,
+    free_inodes_count_lo: U16,
+    used_dirs_count_lo: U16,
+    flags: U16,
+    exclude_bitmap_lo: U32,
+    block_bitmap_csum_lo: U16,
+    inode_bitmap_csum_lo: U16,
+    itable_unused_lo: U16,
+    checksum: U16,
+    block_bitmap_hi: U32,
+    inode_bitmap_hi: U32,
+    inode_table_hi: U32,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -61,10 +305,11 @@ struct BlockGroupDescriptor {
 impl BlockGroupDescriptor {
     const SIZE: u64 = 64;
 
-    fn new(slice: &dyn ReadAt) -> Result<Self> {
-        let r = Reader::new(slice);
+    fn new<V: Volume + ?Sized, S: SectorSize>(dev: &V, base: u64) -> Result<Self> {
+        let raw: BlockGroupDescriptorRaw = Reader::<V, S>::new(dev, base).struct_at(0)?;
         Ok(Self {
-            inode_table: r.u64_lohi(0x8, 0x28)?,
+            inode_table: raw.inode_table_lo.get() as u64
+                | ((raw.inode_table_hi.get() as u64) << 32),
         })
     }
 }
@@ -73,42 +318,608 @@ impl BlockGroupDescriptor {
 struct BlockGroupNumber(u64);
 
 impl BlockGroupNumber {
-    fn desc_slice<T>(self, sb: &Superblock, dev: T) -> Slice<T>
-    where
-        T: ReadAt,
-    {
-        assert!(sb.block_size != 1024, "1024 block size not supported");
-        let gdt_start = sb.block_size;
-        let offset = gdt_start + self.0 * BlockGroupDescriptor::SIZE;
-        Slice::new(dev, offset, None)
+    fn desc_slice(self, sb: &Superblock) -> u64 {
+        // The group descriptor table starts in the block right after the one
+        // holding the superblock. That block is `first_data_block + 1`, which
+        // lands at offset 2048 for 1024-byte-block images (where
+        // `first_data_block == 1`) and one block in for larger block sizes
+        // (where it is 0).
+        let gdt_start = (sb.first_data_block + 1) * sb.block_size;
+        gdt_start + self.0 * BlockGroupDescriptor::SIZE
     }
 
-    fn desc(self, sb: &Superblock, dev: &dyn ReadAt) -> Result<BlockGroupDescriptor> {
-        let slice = self.desc_slice(sb, dev);
-        BlockGroupDescriptor::new(&slice)
+    fn desc<V: Volume + ?Sized, S: SectorSize>(
+        self,
+        sb: &Superblock,
+        dev: &V,
+    ) -> Result<BlockGroupDescriptor> {
+        BlockGroupDescriptor::new::<V, S>(dev, self.desc_slice(sb))
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-struct InodeNumber(u64);
+pub struct InodeNumber(u64);
 
 impl InodeNumber {
     fn blockgroup_number(self, sb: &Superblock) -> BlockGroupNumber {
         let n = (self.0 - 1) / sb.inodes_per_group;
         BlockGroupNumber(n)
     }
+
+    fn inode<V: Volume + ?Sized, S: SectorSize>(
+        self,
+        sb: &Superblock,
+        dev: &V,
+    ) -> Result<Inode> {
+        let bgd = self.blockgroup_number(sb).desc::<V, S>(sb, dev)?;
+        let offset = bgd.inode_table * sb.block_size
+            + ((self.0 - 1) % sb.inodes_per_group) * sb.inode_size;
+        Inode::new::<V, S>(dev, offset)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileType {
+    Regular,
+    Directory,
+    Symlink,
+    Other(u16),
+}
+
+impl FileType {
+    fn from_mode(mode: u16) -> Self {
+        match mode >> 12 {
+            0x8 => Self::Regular,
+            0x4 => Self::Directory,
+            0xA => Self::Symlink,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// The `ext4_extent_header` that prefixes every node of an extent tree, both
+/// the inline `i_block` root and the on-disk index/leaf blocks.
+struct ExtentHeader {
+    entries: u16,
+    depth: u16,
 }
 
+impl ExtentHeader {
+    const MAGIC: u16 = 0xF30A;
+    const SIZE: usize = 12;
+
+    fn parse(buf: &[u8]) -> Self {
+        debug_assert_eq!(u16::from_le_bytes([buf[0], buf[1]]), Self::MAGIC);
+        Self {
+            entries: u16::from_le_bytes([buf[2], buf[3]]),
+            depth: u16::from_le_bytes([buf[6], buf[7]]),
+        }
+    }
+}
+
+/// On-disk `ext4_inode` layout up to and including `i_size_high`, cast from bytes.
+#[derive(FromBytes, FromZeroes)]
+#[repr(C)]
+struct InodeRaw {
+    i_mode: U16,
+    i_uid: U16,
+    i_size_lo: U32,
+    i_atime: U32,
+    i_ctime: U32,
+    i_mtime: U32,
+    i_dtime: U32,
+    i_gid: U16,
+    i_links_count: U16,
+    i_blocks_lo: U32,
+    i_flags: U32,
+    i_osd1: U32,
+    i_block: [u8; 60],
+    i_generation: U32,
+    i_file_acl_lo: U32,
+    i_size_high: U32,
+}
+
+#[derive(CustomDebug)]
+pub struct Inode {
+    #[debug(format = "{:o}")]
+    mode: u16,
+    file_type: FileType,
+    size: u64,
+    #[debug(skip)]
+    block: [u8; 60],
+}
+
+impl Inode {
+    fn new<V: Volume + ?Sized, S: SectorSize>(dev: &V, base: u64) -> Result<Self> {
+        let raw: InodeRaw = Reader::<V, S>::new(dev, base).struct_at(0)?;
+        let mode = raw.i_mode.get();
+        let size = raw.i_size_lo.get() as u64 | ((raw.i_size_high.get() as u64) << 32);
+        Ok(Self {
+            mode,
+            file_type: FileType::from_mode(mode),
+            size,
+            block: raw.i_block,
+        })
+    }
+
+    /// Walk the extent tree in `i_block` to map a logical block number to its
+    /// physical block on the device. `None` means the block is unmapped or
+    /// belongs to an uninitialized (sparse) extent and reads back as zeroes.
+    fn physical_block(&self, logical: u64, sb: &Superblock, dev: &dyn Volume) -> Result<Option<u64>> {
+        let mut node = self.block.to_vec();
+        loop {
+            let header = ExtentHeader::parse(&node);
+            if header.depth == 0 {
+                for i in 0..header.entries as usize {
+                    let e = &node[ExtentHeader::SIZE + i * 12..];
+                    let ee_block = u32::from_le_bytes([e[0], e[1], e[2], e[3]]) as u64;
+                    let mut ee_len = u16::from_le_bytes([e[4], e[5]]) as u64;
+                    let start_hi = u16::from_le_bytes([e[6], e[7]]) as u64;
+                    let start_lo = u32::from_le_bytes([e[8], e[9], e[10], e[11]]) as u64;
+                    // An `ee_len` above 32768 marks an uninitialized extent; the
+                    // real length is the low 15 bits.
+                    let uninitialized = ee_len > 32768;
+                    if uninitialized {
+                        ee_len -= 32768;
+                    }
+                    if logical >= ee_block && logical < ee_block + ee_len {
+                        if uninitialized {
+                            return Ok(None);
+                        }
+                        let phys = (start_hi << 32) | start_lo;
+                        return Ok(Some(phys + (logical - ee_block)));
+                    }
+                }
+                return Ok(None);
+            }
+
+            // Index node: descend into the last child whose `ei_block` is still
+            // at or below the logical block we are looking for. The entries are
+            // followed by a 4-byte tail checksum we don't need to read here.
+            let mut child: Option<u64> = None;
+            for i in 0..header.entries as usize {
+                let e = &node[ExtentHeader::SIZE + i * 12..];
+                let ei_block = u32::from_le_bytes([e[0], e[1], e[2], e[3]]) as u64;
+                let ei_leaf_lo = u32::from_le_bytes([e[4], e[5], e[6], e[7]]) as u64;
+                let ei_leaf_hi = u16::from_le_bytes([e[8], e[9]]) as u64;
+                if logical >= ei_block {
+                    child = Some((ei_leaf_hi << 32) | ei_leaf_lo);
+                } else {
+                    break;
+                }
+            }
+            match child {
+                Some(block) => {
+                    let mut buf = vec![0u8; sb.block_size as usize];
+                    dev.read_at(block * sb.block_size, &mut buf)?;
+                    node = buf;
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Read a single logical block, returning zeroes for sparse/unmapped blocks.
+    fn read_block(&self, logical: u64, sb: &Superblock, dev: &dyn Volume) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; sb.block_size as usize];
+        if let Some(phys) = self.physical_block(logical, sb, dev)? {
+            dev.read_at(phys * sb.block_size, &mut buf)?;
+        }
+        Ok(buf)
+    }
+
+    /// Read the whole inode by reading every logical block up to its size.
+    fn contents(&self, sb: &Superblock, dev: &dyn Volume) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(self.size as usize);
+        let blocks = (self.size + sb.block_size - 1) / sb.block_size;
+        for logical in 0..blocks {
+            data.extend_from_slice(&self.read_block(logical, sb, dev)?);
+        }
+        data.truncate(self.size as usize);
+        Ok(data)
+    }
+
+    /// Iterate the classic linked-list directory records in this inode's data.
+    fn children(&self, sb: &Superblock, dev: &dyn Volume) -> Result<DirEntries> {
+        Ok(DirEntries {
+            data: self.contents(sb, dev)?,
+            offset: 0,
+        })
+    }
+
+    fn child<S: SectorSize>(
+        &self,
+        name: &str,
+        sb: &Superblock,
+        dev: &dyn Volume,
+    ) -> Result<Option<Inode>> {
+        for entry in self.children(sb, dev)? {
+            if entry.name == name {
+                return Ok(Some(
+                    InodeNumber(entry.inode as u64).inode::<dyn Volume, S>(sb, dev)?,
+                ));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// On-disk `ext4_dir_entry_2` fixed header; the variable-length name follows it.
+#[derive(FromBytes, FromZeroes)]
+#[repr(C)]
+struct DirEntryRaw {
+    inode: U32,
+    rec_len: U16,
+    name_len: u8,
+    file_type: u8,
+}
+
+#[derive(Debug)]
+pub struct DirEntry {
+    inode: u32,
+    file_type: u8,
+    name: String,
+}
+
+pub struct DirEntries {
+    data: Vec<u8>,
+    offset: usize,
+}
+
+impl Iterator for DirEntries {
+    type Item = DirEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset + size_of::<DirEntryRaw>() <= self.data.len() {
+            let d = &self.data[self.offset..];
+            let raw = DirEntryRaw::read_from_prefix(d).unwrap();
+            let inode = raw.inode.get();
+            let rec_len = raw.rec_len.get() as usize;
+            let name_len = raw.name_len as usize;
+            let file_type = raw.file_type;
+            if rec_len == 0 {
+                return None;
+            }
+            let name = String::from_utf8_lossy(&d[8..8 + name_len]).into_owned();
+            self.offset += rec_len;
+            if inode != 0 {
+                return Some(DirEntry {
+                    inode,
+                    file_type,
+                    name,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Resolve an absolute path by chaining directory lookups from the root inode.
+fn resolve_path<S: SectorSize>(
+    path: &str,
+    sb: &Superblock,
+    dev: &dyn Volume,
+) -> Result<Option<Inode>> {
+    let mut inode = InodeNumber(2).inode::<dyn Volume, S>(sb, dev)?;
+    for component in path.split('/').filter(|c| !c.is_empty()) {
+        match inode.child::<S>(component, sb, dev)? {
+            Some(child) => inode = child,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(inode))
+}
+
+/// Number of physical blocks kept in each handle's LRU cache.
+#[cfg(feature = "std")]
+const BLOCK_CACHE_CAPACITY: usize = 256;
+
+/// A read-only ext4 filesystem: the device, its parsed superblock, a lazily
+/// filled table of block-group descriptors, and an LRU cache of physical
+/// blocks so repeated directory walks and extent lookups don't re-read the
+/// device. Reading through the handle (as a [`Volume`]) goes through the cache.
+#[cfg(feature = "std")]
+pub struct Ext4 {
+    dev: Box<dyn Volume + Send + Sync>,
+    sb: Superblock,
+    descriptors: Mutex<Vec<Option<BlockGroupDescriptor>>>,
+    cache: Mutex<LruCache<u64, Vec<u8>>>,
+}
+
+#[cfg(feature = "std")]
+impl Ext4 {
+    pub fn open(dev: impl Volume + Send + Sync + 'static) -> Result<Self> {
+        let sb = Superblock::new::<_, Size512>(&dev)?;
+        let groups = sb.block_group_count()? as usize;
+        let capacity = NonZeroUsize::new(BLOCK_CACHE_CAPACITY).unwrap();
+        Ok(Self {
+            dev: Box::new(dev),
+            sb,
+            descriptors: Mutex::new(vec![None; groups]),
+            cache: Mutex::new(LruCache::new(capacity)),
+        })
+    }
+
+    pub fn root(&self) -> Result<Inode> {
+        self.inode(InodeNumber(2))
+    }
+
+    pub fn inode(&self, number: InodeNumber) -> Result<Inode> {
+        let bgd = self.descriptor(number.blockgroup_number(&self.sb))?;
+        let offset = bgd.inode_table * self.sb.block_size
+            + ((number.0 - 1) % self.sb.inodes_per_group) * self.sb.inode_size;
+        Inode::new::<Ext4, Size512>(self, offset)
+    }
+
+    /// Resolve an absolute path to its inode, walking from the root directory.
+    /// Reads route through the handle, so the block cache is shared across the
+    /// whole walk.
+    pub fn resolve(&self, path: &str) -> Result<Option<Inode>> {
+        resolve_path::<Size512>(path, &self.sb, self)
+    }
+
+    /// Iterate the directory entries of an inode, reading through the cache.
+    pub fn children(&self, inode: &Inode) -> Result<DirEntries> {
+        inode.children(&self.sb, self)
+    }
+
+    /// Read an inode's full contents, reading through the cache.
+    pub fn contents(&self, inode: &Inode) -> Result<Vec<u8>> {
+        inode.contents(&self.sb, self)
+    }
+
+    /// Fetch a block-group descriptor, reading and caching it on first use.
+    fn descriptor(&self, group: BlockGroupNumber) -> Result<BlockGroupDescriptor> {
+        let mut table = self.descriptors.lock().unwrap();
+        if let Some(desc) = table[group.0 as usize] {
+            return Ok(desc);
+        }
+        let desc = group.desc::<Ext4, Size512>(&self.sb, self)?;
+        table[group.0 as usize] = Some(desc);
+        Ok(desc)
+    }
+
+    /// Read a whole physical block, serving it from the LRU cache when present.
+    fn cached_block(&self, block: u64) -> Result<Vec<u8>> {
+        if let Some(data) = self.cache.lock().unwrap().get(&block) {
+            return Ok(data.clone());
+        }
+        let mut buf = vec![0u8; self.sb.block_size as usize];
+        self.dev.read_at(block * self.sb.block_size, &mut buf)?;
+        self.cache.lock().unwrap().put(block, buf.clone());
+        Ok(buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Volume for Ext4 {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let bs = self.sb.block_size;
+        let mut filled = 0;
+        let mut pos = offset;
+        while filled < buf.len() {
+            let block = self.cached_block(pos / bs)?;
+            let within = (pos % bs) as usize;
+            let take = core::cmp::min(bs as usize - within, buf.len() - filled);
+            buf[filled..filled + take].copy_from_slice(&block[within..within + take]);
+            filled += take;
+            pos += take as u64;
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> Result<u64> {
+        self.dev.len()
+    }
+}
+
+/// A cheaply-cloneable, thread-safe handle sharing one [`Ext4`] behind an
+/// `Arc<Mutex<..>>`, so inode and directory reads can be driven from many
+/// threads at once.
+#[cfg(feature = "std")]
+pub struct Synced<T>(Arc<Mutex<T>>);
+
+#[cfg(feature = "std")]
+impl<T> Clone for Synced<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Synced<Ext4> {
+    pub fn open(dev: impl Volume + Send + Sync + 'static) -> Result<Self> {
+        Ok(Self(Arc::new(Mutex::new(Ext4::open(dev)?))))
+    }
+
+    pub fn root(&self) -> Result<Inode> {
+        self.0.lock().unwrap().root()
+    }
+
+    pub fn inode(&self, number: InodeNumber) -> Result<Inode> {
+        self.0.lock().unwrap().inode(number)
+    }
+
+    pub fn resolve(&self, path: &str) -> Result<Option<Inode>> {
+        self.0.lock().unwrap().resolve(path)
+    }
+
+    pub fn children(&self, inode: &Inode) -> Result<DirEntries> {
+        self.0.lock().unwrap().children(inode)
+    }
+
+    pub fn contents(&self, inode: &Inode) -> Result<Vec<u8>> {
+        self.0.lock().unwrap().contents(inode)
+    }
+}
+
+#[cfg(feature = "std")]
 fn main() -> Result<()> {
     let file = OpenOptions::new()
         .read(true)
         .open("/dev/mapper/ubuntu--vg-ubuntu--lv")?;
 
-    let sb = Superblock::new(&file)?;
-    println!("{sb:#?}");
+    let fs = Synced::<Ext4>::open(file)?;
+    let root = fs.root()?;
+    println!("{root:#?}");
 
-    let bgd = InodeNumber(2).blockgroup_number(&sb).desc(&sb, &file)?;
-    println!("{bgd:#?}");
+    for entry in fs.children(&root)? {
+        println!("{entry:?}");
+    }
+
+    if let Some(inode) = fs.resolve("/etc/hostname")? {
+        let data = fs.contents(&inode)?;
+        println!("{}", String::from_utf8_lossy(&data));
+    }
 
     Ok(())
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    /// A trivial in-memory [`Volume`] backed by a byte image.
+    struct MemVolume(Vec<u8>);
+
+    impl Volume for MemVolume {
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+            let start = offset as usize;
+            buf.copy_from_slice(&self.0[start..start + buf.len()]);
+            Ok(())
+        }
+
+        fn len(&self) -> Result<u64> {
+            Ok(self.0.len() as u64)
+        }
+    }
+
+    fn put_u8(img: &mut [u8], off: usize, v: u8) {
+        img[off] = v;
+    }
+    fn put_u16(img: &mut [u8], off: usize, v: u16) {
+        img[off..off + 2].copy_from_slice(&v.to_le_bytes());
+    }
+    fn put_u32(img: &mut [u8], off: usize, v: u32) {
+        img[off..off + 4].copy_from_slice(&v.to_le_bytes());
+    }
+
+    /// Hand-assemble the smallest ext4 image the reader can walk: a 1024-byte
+    /// block filesystem with a single block group, a root directory holding one
+    /// regular file `hello`, each file laid out via a one-entry extent tree.
+    fn fixture() -> MemVolume {
+        const BS: usize = 1024;
+        let mut img = vec![0u8; 12 * BS];
+
+        // Superblock at byte 1024.
+        let sb = BS;
+        put_u32(&mut img, sb, 8); // inodes_count
+        put_u32(&mut img, sb + 0x04, 33); // blocks_count_lo
+        put_u32(&mut img, sb + 0x14, 1); // first_data_block
+        put_u32(&mut img, sb + 0x18, 0); // log_block_size -> 1024
+        put_u32(&mut img, sb + 0x20, 64); // blocks_per_group
+        put_u32(&mut img, sb + 0x28, 8); // inodes_per_group
+        put_u16(&mut img, sb + 0x38, 0xEF53); // magic
+        put_u16(&mut img, sb + 0x58, 128); // inode_size
+
+        // Group descriptor table at (first_data_block + 1) * BS = 2048.
+        let gdt = 2 * BS;
+        put_u32(&mut img, gdt + 0x08, 5); // inode_table starts at block 5
+
+        // Inode table at block 5; inode N lives at 5*BS + ((N-1) % 8) * 128.
+        let itable = 5 * BS;
+        let root = itable + 128; // inode 2
+        let hello = itable + 2 * 128; // inode 3
+
+        // Write a one-entry depth-0 extent tree at the inode's i_block (0x28).
+        let extent = |img: &mut [u8], inode: usize, phys: u32| {
+            let h = inode + 0x28;
+            put_u16(img, h, 0xF30A); // eh_magic
+            put_u16(img, h + 2, 1); // eh_entries
+            put_u16(img, h + 4, 4); // eh_max
+            put_u16(img, h + 6, 0); // eh_depth
+            let e = h + 12;
+            put_u32(img, e, 0); // ee_block
+            put_u16(img, e + 4, 1); // ee_len
+            put_u16(img, e + 6, 0); // ee_start_hi
+            put_u32(img, e + 8, phys); // ee_start_lo
+        };
+
+        // Root directory (inode 2): one block of linked dir records at block 10.
+        put_u16(&mut img, root, 0x41ED); // i_mode: directory
+        put_u32(&mut img, root + 0x04, BS as u32); // i_size_lo
+        extent(&mut img, root, 10);
+
+        let dir = 10 * BS;
+        // "." -> 2
+        put_u32(&mut img, dir, 2);
+        put_u16(&mut img, dir + 4, 12);
+        put_u8(&mut img, dir + 6, 1);
+        put_u8(&mut img, dir + 7, 2);
+        img[dir + 8..dir + 9].copy_from_slice(b".");
+        // ".." -> 2
+        put_u32(&mut img, dir + 12, 2);
+        put_u16(&mut img, dir + 16, 12);
+        put_u8(&mut img, dir + 18, 2);
+        put_u8(&mut img, dir + 19, 2);
+        img[dir + 20..dir + 22].copy_from_slice(b"..");
+        // "hello" -> 3, rec_len spans the rest of the block
+        put_u32(&mut img, dir + 24, 3);
+        put_u16(&mut img, dir + 28, (BS - 24) as u16);
+        put_u8(&mut img, dir + 30, 5);
+        put_u8(&mut img, dir + 31, 1);
+        img[dir + 32..dir + 37].copy_from_slice(b"hello");
+
+        // Regular file (inode 3): three bytes "hi\n" in block 11.
+        put_u16(&mut img, hello, 0x81A4); // i_mode: regular
+        put_u32(&mut img, hello + 0x04, 3); // i_size_lo
+        extent(&mut img, hello, 11);
+        img[11 * BS..11 * BS + 3].copy_from_slice(b"hi\n");
+
+        MemVolume(img)
+    }
+
+    #[test]
+    fn walks_directory_and_reads_file() {
+        let vol = fixture();
+
+        let sb = Superblock::new::<MemVolume, Size512>(&vol).unwrap();
+        assert_eq!(sb.block_size, 1024);
+        assert_eq!(sb.block_group_count().unwrap(), 1);
+
+        let root = InodeNumber(2).inode::<MemVolume, Size512>(&sb, &vol).unwrap();
+        assert_eq!(root.file_type, FileType::Directory);
+
+        let names: Vec<String> = root.children(&sb, &vol).unwrap().map(|e| e.name).collect();
+        assert_eq!(names, vec![".", "..", "hello"]);
+
+        let hello = resolve_path::<Size512>("/hello", &sb, &vol)
+            .unwrap()
+            .expect("hello resolves");
+        assert_eq!(hello.file_type, FileType::Regular);
+        assert_eq!(hello.size, 3);
+        assert_eq!(hello.contents(&sb, &vol).unwrap(), b"hi\n");
+
+        assert!(resolve_path::<Size512>("/missing", &sb, &vol)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn reader_honours_sector_size() {
+        let vol = fixture();
+
+        // The same image parses identically regardless of the sector size the
+        // reader addresses it in.
+        assert_eq!(Size512::SIZE, 512);
+        assert_eq!(Size2048::SIZE, 2048);
+        assert_eq!(Size4096::SIZE, 4096);
+        for expect in [
+            Superblock::new::<MemVolume, Size2048>(&vol).unwrap(),
+            Superblock::new::<MemVolume, Size4096>(&vol).unwrap(),
+        ] {
+            assert_eq!(expect.block_size, 1024);
+            assert_eq!(expect.block_group_count().unwrap(), 1);
+        }
+    }
+}